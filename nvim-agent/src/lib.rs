@@ -59,4 +59,23 @@ impl NeovimClient {
     pub fn print<S: AsRef<str>>(&mut self, msg: S) {
         _ = self.nvim_command(format!("echo '{}'", msg.as_ref()));
     }
+
+    /// set the content of register `regname`, use `+` to write into system clipboard
+    pub fn setreg<S1: AsRef<str>, S2: AsRef<str>>(
+        &mut self,
+        regname: S1,
+        content: S2,
+    ) -> Result<()> {
+        self.nvim_call_function(
+            "setreg".to_owned(),
+            vec![Value::from(regname.as_ref()), Value::from(content.as_ref())],
+        )
+        .map(|_| ())
+    }
+
+    /// put `text` after the cursor and move the cursor to the end of the text
+    pub fn put<S: AsRef<str>>(&mut self, text: S) -> Result<()> {
+        let lines = text.as_ref().split('\n').map(Value::from).collect();
+        self.nvim_put(lines, "c".to_owned(), true, true)
+    }
 }
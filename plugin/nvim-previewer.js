@@ -29,10 +29,53 @@
     document.removeEventListener("copy", listener);
   }
 
+  function uploadAsset(file) {
+    let token = document.querySelector("meta[name='previewer-token']").content;
+    let url = '/api/assets?name=' + encodeURIComponent(file.name || '');
+    fetch(url, {
+      method: 'POST',
+      headers: {
+        'Content-Type': file.type || 'application/octet-stream',
+        'X-Previewer-Token': token,
+      },
+      body: file,
+    }).then(function(resp) {
+      if (!resp.ok) {
+        resp.text().then(function(msg) { console.error('upload failed: ' + msg); });
+      }
+    });
+  }
+
+  function uploadFiles(files) {
+    let items = Array.from(files);
+    items.forEach(uploadAsset);
+    return items.length > 0;
+  }
+
   document.addEventListener('DOMContentLoaded', function(event) {
     // placeholder
   });
 
+  // Pasted or dropped files are saved next to the previewed file and linked into the buffer
+  document.addEventListener('paste', function(event) {
+    if (uploadFiles(event.clipboardData.files)) {
+      event.preventDefault();
+    }
+  });
+  document.addEventListener('dragover', function(event) {
+    event.preventDefault();
+  });
+  document.addEventListener('drop', function(event) {
+    if (uploadFiles(event.dataTransfer.files)) {
+      event.preventDefault();
+    }
+  });
+
+  // Headless chromium reads the page height to capture the whole page as an image
+  function measurePageHeight() {
+    document.body.setAttribute('data-page-height', document.documentElement.scrollHeight);
+  }
+
   window.addEventListener('load', function() {
     // Let's make it compatible with the troublesome WeChat Official Account
     for (let mjx of document.querySelectorAll("mjx-container[display='true']")) {
@@ -44,6 +87,11 @@
       mjx.style["margin-left"] = "0.5em";
       mjx.style["margin-right"] = "0.5em";
     }
+
+    measurePageHeight();
+    if (MathJax.startup && MathJax.startup.promise) {
+      MathJax.startup.promise.then(measurePageHeight);
+    }
   });
 </script>
 
@@ -48,3 +48,5 @@ endif
 let s:script_dir = expand('<sfile>:p:h')
 command! -nargs=0 Preview    call rpcnotify(s:jobid, 'preview', expand('%:p'), s:script_dir)
 command! -nargs=0 PreviewAlt call rpcnotify(s:jobid, 'preview_alt', expand('%:p'), s:script_dir)
+command! -nargs=? -range=% PreviewCopy call rpcnotify(s:jobid, 'copy', expand('%:p'), s:script_dir, <q-args>, <line1>, <line2>)
+command! -nargs=0 PreviewAccessible call rpcnotify(s:jobid, 'preview_a11y', expand('%:p'), s:script_dir)
@@ -0,0 +1,132 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{anyerr, error::Result};
+
+const CANDIDATES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+];
+// the attribute set on `<body>` by nvim-previewer.js once the page is laid out
+const PAGE_HEIGHT_ATTR: &str = "data-page-height=\"";
+// time given to the page to run scripts such as MathJax before capturing
+const VIRTUAL_TIME_BUDGET: u32 = 5000;
+const MAX_PAGE_HEIGHT: u32 = 16384;
+// the chromium binary found in well known names, searched only once
+static DISCOVERED_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    CANDIDATES.iter().map(PathBuf::from).find(|candidate| {
+        Command::new(candidate)
+            .arg("--version")
+            .output()
+            .map(|x| x.status.success())
+            .unwrap_or(false)
+    })
+});
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum ImageFormat {
+    #[default]
+    #[serde(rename = "png")]
+    Png,
+    #[serde(rename = "jpeg")]
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn ext(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+/// A headless chromium backend driven by its command line, the rendered page is loaded from the
+/// previewer web server.
+pub struct Chromium {
+    path: PathBuf,
+}
+
+impl Chromium {
+    /// Use the chromium binary `path` if it is given, or else search the well known names
+    pub fn new(path: Option<&str>) -> Result<Self> {
+        if let Some(path) = path {
+            return Ok(Self {
+                path: PathBuf::from(path),
+            });
+        }
+        let path = DISCOVERED_PATH.clone().ok_or(anyerr!(
+            "chromium is not found, set g:nvim_previewer_chromium"
+        ))?;
+        Ok(Self { path })
+    }
+
+    pub fn run(&self, args: &[String]) -> Result<Output> {
+        let output = Command::new(&self.path)
+            .arg("--headless")
+            .arg("--disable-gpu")
+            .arg("--hide-scrollbars")
+            .arg(format!("--virtual-time-budget={VIRTUAL_TIME_BUDGET}"))
+            .args(args)
+            .output()
+            .map_err(|e| {
+                anyerr!("failed to run {}: {e:?}", self.path.display())
+            })?;
+        if !output.status.success() {
+            let errmsg = String::from_utf8(output.stderr)
+                .unwrap_or("failed to run".to_owned());
+            return Err(anyerr!("chromium exit with error: {errmsg}").into());
+        }
+        Ok(output)
+    }
+
+    /// Get the height of the whole page at `url` when its viewport width is `width`
+    pub fn page_height(&self, url: &str, width: u32) -> Result<u32> {
+        let output = self.run(&[
+            format!("--window-size={width},800"),
+            "--dump-dom".to_owned(),
+            url.to_owned(),
+        ])?;
+        let dom = String::from_utf8_lossy(&output.stdout);
+        let height = dom
+            .find(PAGE_HEIGHT_ATTR)
+            .map(|i| &dom[i + PAGE_HEIGHT_ATTR.len()..])
+            .and_then(|x| x.split('"').next())
+            .and_then(|x| x.parse::<u32>().ok())
+            .ok_or(anyerr!("failed to measure page height"))?;
+        Ok(height.min(MAX_PAGE_HEIGHT))
+    }
+
+    /// Capture the whole page at `url` into image `output`, its format is decided by the
+    /// extension of `output`.
+    pub fn screenshot(
+        &self,
+        url: &str,
+        width: u32,
+        output: &Path,
+    ) -> Result<()> {
+        let height = self.page_height(url, width)?;
+        self.run(&[
+            format!("--window-size={width},{height}"),
+            format!("--screenshot={}", output.display()),
+            url.to_owned(),
+        ])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    env::var,
+    io::{Read, Write},
+    process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+use crate::{anyerr, error::Result};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum CopyFormat {
+    #[default]
+    #[serde(rename = "html")]
+    Html,
+    #[serde(rename = "rtf")]
+    Rtf,
+}
+
+impl CopyFormat {
+    pub fn new<S: AsRef<str>>(format: S) -> Self {
+        match format.as_ref().trim() {
+            "rtf" => CopyFormat::Rtf,
+            _ => CopyFormat::Html,
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            CopyFormat::Html => "text/html",
+            CopyFormat::Rtf => "text/rtf",
+        }
+    }
+}
+
+/// Extract the inner html of `<body>` from a full html document, the whole document is returned
+/// if there is no body.
+pub fn body_of(html: &str) -> &str {
+    let start = html
+        .find("<body")
+        .and_then(|i| html[i..].find('>').map(|j| i + j + 1));
+    let end = html.rfind("</body>");
+    match (start, end) {
+        (Some(s), Some(e)) if s <= e => html[s..e].trim(),
+        _ => html,
+    }
+}
+
+/// Remove attributes `names` from all tags in `html`, style-inlined fragment does not need them
+/// and they may clash with the stylesheets of the application where the fragment is pasted.
+pub fn strip_attrs(html: &str, names: &[&str]) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        output.push_str(&rest[..lt]);
+        rest = &rest[lt..];
+        let gt = if let Some(gt) = rest.find('>') {
+            gt
+        } else {
+            break;
+        };
+        let mut tag = rest[..=gt].to_owned();
+        for name in names {
+            let pattern = format!(" {name}=\"");
+            while let Some(s) = tag.find(&pattern) {
+                let vs = s + pattern.len();
+                let ve = tag[vs..].find('"').map(|e| vs + e + 1);
+                match ve {
+                    Some(ve) => tag.replace_range(s..ve, ""),
+                    None => break,
+                }
+            }
+        }
+        output.push_str(&tag);
+        rest = &rest[gt + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+fn escape_rtf(text: &str, preformatted: bool) -> String {
+    let mut output = String::new();
+    for c in decode_entities(text).chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                output.push('\\');
+                output.push(c);
+            }
+            '\n' if preformatted => output.push_str("\\line "),
+            '\n' | '\r' => output.push(' '),
+            c if c.is_ascii() => output.push(c),
+            c => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    output.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    output
+}
+
+fn attr_of(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=\"");
+    let s = tag.find(&pattern)? + pattern.len();
+    let e = tag[s..].find('"')? + s;
+    Some(decode_entities(&tag[s..e]))
+}
+
+/// Convert a html fragment into a RTF document
+///
+/// Only the structure commonly produced by concisemark is kept (headings, paragraphs, lists,
+/// emphasis, code and links), styles of other tags are dropped and only their text is preserved.
+pub fn html_to_rtf(html: &str) -> String {
+    let mut rtf = String::from(
+        "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Helvetica;}{\\f1 Courier New;}}\\fs24\n",
+    );
+    let mut pre_depth = 0;
+    let mut rest = html;
+    while !rest.is_empty() {
+        let lt = rest.find('<').unwrap_or(rest.len());
+        rtf.push_str(&escape_rtf(&rest[..lt], pre_depth > 0));
+        rest = &rest[lt..];
+        let gt = if let Some(gt) = rest.find('>') {
+            gt
+        } else {
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        let piece = match (name.as_str(), closing) {
+            ("p" | "div" | "blockquote", false) => "\\pard ".to_owned(),
+            ("p" | "div" | "blockquote", true) => "\\par\n".to_owned(),
+            ("h1", false) => "{\\pard\\b\\fs40 ".to_owned(),
+            ("h2", false) => "{\\pard\\b\\fs36 ".to_owned(),
+            ("h3", false) => "{\\pard\\b\\fs32 ".to_owned(),
+            ("h4" | "h5" | "h6", false) => "{\\pard\\b\\fs28 ".to_owned(),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                "\\par}\n".to_owned()
+            }
+            ("strong" | "b", false) => "{\\b ".to_owned(),
+            ("em" | "i", false) => "{\\i ".to_owned(),
+            ("code", false) => "{\\f1 ".to_owned(),
+            ("strong" | "b" | "em" | "i" | "code", true) => "}".to_owned(),
+            ("pre", false) => {
+                pre_depth += 1;
+                "{\\pard\\f1\\fs20 ".to_owned()
+            }
+            ("pre", true) => {
+                pre_depth -= 1;
+                "\\par}\n".to_owned()
+            }
+            ("li", false) => "{\\pard\\fi-200\\li400 \\bullet\\tab ".to_owned(),
+            ("li", true) => "\\par}\n".to_owned(),
+            ("br", _) => "\\line ".to_owned(),
+            ("a", false) => {
+                // a quote would end the field instruction
+                let href = attr_of(tag, "href")
+                    .unwrap_or_default()
+                    .replace('"', "%22");
+                format!(
+                    "{{\\field{{\\*\\fldinst{{HYPERLINK \"{}\"}}}}{{\\fldrslt{{\\ul ",
+                    escape_rtf(&href, false)
+                )
+            }
+            ("a", true) => "}}}".to_owned(),
+            ("img", _) => {
+                let alt = attr_of(tag, "alt").unwrap_or("image".to_owned());
+                format!("[{}]", escape_rtf(&alt, false))
+            }
+            _ => "".to_owned(),
+        };
+        rtf.push_str(&piece);
+    }
+    rtf.push('}');
+    rtf
+}
+
+/// Put `content` onto the system clipboard tagged with the mime type of `format`, so that it is
+/// pasted as rich text rather than raw markup.
+pub fn set_system_clipboard(content: &str, format: CopyFormat) -> Result<()> {
+    let (program, args) = if cfg!(target_os = "macos") {
+        // pbcopy is unable to tag html content, but it detects rtf from the `{\rtf` header
+        ("pbcopy", vec![])
+    } else if cfg!(target_os = "linux") && var("WAYLAND_DISPLAY").is_ok() {
+        ("wl-copy", vec!["--type", format.mime()])
+    } else if cfg!(target_os = "linux") {
+        (
+            "xclip",
+            vec!["-selection", "clipboard", "-t", format.mime()],
+        )
+    } else {
+        return Err(anyerr!("rich text clipboard is not supported").into());
+    };
+    let content = if cfg!(target_os = "macos") && format == CopyFormat::Html {
+        html_to_rtf(content)
+    } else {
+        content.to_owned()
+    };
+    // stdout of the previewer is the rpc channel of neovim, and xclip or wl-copy keeps running in
+    // the background to serve the clipboard, so they must never write into it
+    let mut child = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyerr!("failed to run {program}: {e:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(content.as_bytes())
+            .map_err(|e| anyerr!("failed to write to {program}: {e:?}"))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| anyerr!("failed to wait {program}: {e:?}"))?;
+    if !status.success() {
+        // the tool fails before going to the background, nothing else holds its stderr
+        let mut errmsg = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            _ = stderr.read_to_string(&mut errmsg);
+        }
+        return Err(anyerr!(
+            "{program} exit with error: {status}: {}",
+            errmsg.trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RTF_HEADER: &str =
+        "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Helvetica;}{\\f1 Courier New;}}\\fs24\n";
+
+    fn rtf_body(html: &str) -> String {
+        let rtf = html_to_rtf(html);
+        assert!(rtf.starts_with(RTF_HEADER) && rtf.ends_with('}'));
+        rtf[RTF_HEADER.len()..rtf.len() - 1].to_owned()
+    }
+
+    #[test]
+    fn test_body_of() {
+        let html =
+            "<html><head></head><body style=\"a\">\n<p>x</p>\n</body></html>";
+        assert_eq!(body_of(html), "<p>x</p>");
+        assert_eq!(body_of("<p>x</p>"), "<p>x</p>");
+    }
+
+    #[test]
+    fn test_strip_attrs() {
+        let html = "<p class=\"a\" style=\"color: red\" id=\"b\">x > y</p><br>";
+        assert_eq!(
+            strip_attrs(html, &["class", "id"]),
+            "<p style=\"color: red\">x > y</p><br>"
+        );
+        assert_eq!(strip_attrs("a <b", &["class"]), "a <b");
+    }
+
+    #[test]
+    fn test_rtf_nested_inline() {
+        assert_eq!(
+            rtf_body("<p><strong>bold <em>both</em></strong> plain</p>"),
+            "\\pard {\\b bold {\\i both}} plain\\par\n"
+        );
+    }
+
+    #[test]
+    fn test_rtf_preformatted() {
+        assert_eq!(
+            rtf_body("<pre><code>a {\nb}</code></pre>\n<p>c\nd</p>"),
+            "{\\pard\\f1\\fs20 {\\f1 a \\{\\line b\\}}\\par}\n \\pard c d\\par\n"
+        );
+    }
+
+    #[test]
+    fn test_rtf_unicode() {
+        assert_eq!(rtf_body("中&amp;"), "\\u20013?&");
+        // characters out of the basic plane are written as utf-16 surrogates
+        assert_eq!(rtf_body("\u{1f600}"), "\\u-10179?\\u-8704?");
+    }
+
+    #[test]
+    fn test_rtf_link() {
+        assert_eq!(
+            rtf_body("<a style=\"x\" href=\"https://a.b/?q=&quot;c&quot;&amp;d\">l</a>"),
+            "{\\field{\\*\\fldinst{HYPERLINK \"https://a.b/?q=%22c%22&d\"}}{\\fldrslt{\\ul l}}}"
+        );
+        assert_eq!(
+            rtf_body("<img src=\"a.png\" alt=\"a {b}\"><img src=\"c.png\">"),
+            "[a \\{b\\}][image]"
+        );
+    }
+}
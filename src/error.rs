@@ -1,4 +1,7 @@
-use axum::response::{IntoResponse, Response};
+use axum::{
+    http::status::StatusCode,
+    response::{IntoResponse, Response},
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -24,9 +27,23 @@ macro_rules! anyerr {
     };
 }
 
+/// The message of a response built from [`Error`], it is used to localize the response
+#[derive(Clone, Debug)]
+pub struct ErrorMessage(pub String);
+
+impl Error {
+    /// Build the response of `status`, handlers whose clients check the status of the response
+    /// should use it instead of the `200 OK` one of [`IntoResponse`].
+    pub fn into_response_with(self, status: StatusCode) -> Response {
+        let msg = format!("{self:?}");
+        let mut resp = (status, msg.clone()).into_response();
+        resp.extensions_mut().insert(ErrorMessage(msg));
+        resp
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        (axum::http::status::StatusCode::OK, format!("{self:?}"))
-            .into_response()
+        self.into_response_with(StatusCode::OK)
     }
 }
@@ -0,0 +1,194 @@
+/// User interface strings of the previewer in one language, `{}` in a string is replaced by its
+/// argument.
+pub struct Locale {
+    /// primary language subtag
+    pub lang: &'static str,
+    pub menu: &'static str,
+    pub view_pdf: &'static str,
+    pub view_latex: &'static str,
+    pub view_image: &'static str,
+    pub copy_html: &'static str,
+    pub skip_to_content: &'static str,
+    pub a11y_warnings: &'static str,
+    pub missing_alt: &'static str,
+    pub no_file: &'static str,
+    pub open_failed: &'static str,
+    pub read_failed: &'static str,
+    pub css_not_found: &'static str,
+    pub no_route: &'static str,
+    pub request_failed: &'static str,
+    pub invalid_host: &'static str,
+    pub invalid_token: &'static str,
+    pub empty_asset: &'static str,
+    /// chrono format of the date in page header and PDF title
+    pub date_format: &'static str,
+}
+
+const EN: Locale = Locale {
+    lang: "en",
+    menu: "Export",
+    view_pdf: "View as PDF",
+    view_latex: "View Latex Source",
+    view_image: "View as Image",
+    copy_html: "COPY HTML",
+    skip_to_content: "Skip to content",
+    a11y_warnings: "Accessibility warnings",
+    missing_alt: "Image {} has no alternative text",
+    no_file: "no file to render",
+    open_failed: "failed to open file: {}",
+    read_failed: "can not read file: {}",
+    css_not_found: "css file not found",
+    no_route: "No route for {}",
+    request_failed: "failed to handle the request: {}",
+    invalid_host: "invalid host",
+    invalid_token: "invalid previewer token",
+    empty_asset: "empty asset",
+    date_format: "%Y-%m-%d %H:%M:%S",
+};
+
+const ZH: Locale = Locale {
+    lang: "zh",
+    menu: "导出",
+    view_pdf: "查看 PDF",
+    view_latex: "查看 LaTeX 源码",
+    view_image: "查看图片",
+    copy_html: "复制 HTML",
+    skip_to_content: "跳到正文",
+    a11y_warnings: "无障碍警告",
+    missing_alt: "图片 {} 缺少替代文本",
+    no_file: "没有需要渲染的文件",
+    open_failed: "无法打开文件: {}",
+    read_failed: "无法读取文件: {}",
+    css_not_found: "找不到 css 文件",
+    no_route: "找不到路由 {}",
+    request_failed: "请求处理失败: {}",
+    invalid_host: "无效的主机",
+    invalid_token: "无效的预览令牌",
+    empty_asset: "文件内容为空",
+    date_format: "%Y年%m月%d日 %H:%M:%S",
+};
+
+const DE: Locale = Locale {
+    lang: "de",
+    menu: "Exportieren",
+    view_pdf: "Als PDF anzeigen",
+    view_latex: "LaTeX-Quelltext anzeigen",
+    view_image: "Als Bild anzeigen",
+    copy_html: "HTML KOPIEREN",
+    skip_to_content: "Zum Inhalt springen",
+    a11y_warnings: "Hinweise zur Barrierefreiheit",
+    missing_alt: "Bild {} hat keinen Alternativtext",
+    no_file: "keine Datei zum Anzeigen",
+    open_failed: "Datei kann nicht geöffnet werden: {}",
+    read_failed: "Datei kann nicht gelesen werden: {}",
+    css_not_found: "CSS-Datei nicht gefunden",
+    no_route: "Keine Route für {}",
+    request_failed: "Anfrage fehlgeschlagen: {}",
+    invalid_host: "Ungültiger Host",
+    invalid_token: "Ungültiges Previewer-Token",
+    empty_asset: "Leere Datei",
+    date_format: "%d.%m.%Y %H:%M:%S",
+};
+
+const FR: Locale = Locale {
+    lang: "fr",
+    menu: "Exporter",
+    view_pdf: "Afficher en PDF",
+    view_latex: "Afficher la source LaTeX",
+    view_image: "Afficher en image",
+    copy_html: "COPIER LE HTML",
+    skip_to_content: "Aller au contenu",
+    a11y_warnings: "Avertissements d'accessibilité",
+    missing_alt: "L'image {} n'a pas de texte alternatif",
+    no_file: "aucun fichier à afficher",
+    open_failed: "impossible d'ouvrir le fichier : {}",
+    read_failed: "impossible de lire le fichier : {}",
+    css_not_found: "fichier css introuvable",
+    no_route: "Aucune route pour {}",
+    request_failed: "échec du traitement de la requête : {}",
+    invalid_host: "hôte invalide",
+    invalid_token: "jeton du prévisualiseur invalide",
+    empty_asset: "fichier vide",
+    date_format: "%d/%m/%Y %H:%M:%S",
+};
+
+const LOCALES: &[Locale] = &[EN, ZH, DE, FR];
+
+impl Locale {
+    /// Find the locale of language tag `lang` such as `zh-CN`
+    pub fn find(lang: &str) -> Option<&'static Locale> {
+        let primary = lang.trim().split(['-', '_']).next()?.to_lowercase();
+        LOCALES.iter().find(|x| x.lang == primary)
+    }
+
+    /// Select the locale of the document language `doclang`, the `configured` one is used if the
+    /// document language is not given or not supported, and english is the last resort.
+    pub fn select(
+        doclang: Option<&str>,
+        configured: Option<&str>,
+    ) -> &'static Locale {
+        doclang
+            .and_then(Locale::find)
+            .or_else(|| configured.and_then(Locale::find))
+            .unwrap_or(&LOCALES[0])
+    }
+
+    pub fn fill<S: AsRef<str>>(pattern: &str, arg: S) -> String {
+        pattern.replacen("{}", arg.as_ref(), 1)
+    }
+}
+
+/// Get the value of `lang:` from the metadata block at the beginning of the document, it should
+/// only be called for documents which have metadata.
+pub fn frontmatter_lang(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(|x| x.trim())
+        .skip_while(|x| x.is_empty())
+        .take_while(|x| !x.is_empty())
+        .find_map(|x| x.strip_prefix("lang:"))
+        .map(|x| x.trim().trim_matches(|c| c == '"' || c == '\'').to_owned())
+        .filter(|x| !x.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontmatter_lang() {
+        assert_eq!(
+            frontmatter_lang("\n---\ntitle: a\nlang: \"zh-CN\"\n---\n\nb"),
+            Some("zh-CN".to_owned())
+        );
+        // the metadata block ends at the first blank line
+        assert_eq!(frontmatter_lang("title: a\n\nlang: zh\n"), None);
+        assert_eq!(frontmatter_lang("title: a\nlang: ''\n"), None);
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(Locale::find("zh-CN").map(|x| x.lang), Some("zh"));
+        assert_eq!(Locale::find("zh_CN").map(|x| x.lang), Some("zh"));
+        assert_eq!(Locale::find(" DE ").map(|x| x.lang), Some("de"));
+        assert!(Locale::find("ja").is_none());
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(Locale::select(Some("fr-CA"), Some("zh")).lang, "fr");
+        // an unsupported document language falls back to the configured one
+        assert_eq!(Locale::select(Some("ja"), Some("de")).lang, "de");
+        assert_eq!(Locale::select(Some("ja"), Some("ko")).lang, "en");
+        assert_eq!(Locale::select(None, None).lang, "en");
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            Locale::fill(EN.missing_alt, "a.png"),
+            "Image a.png has no alternative text"
+        );
+        assert_eq!(Locale::fill(EN.menu, "x"), "Export");
+    }
+}
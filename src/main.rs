@@ -1,47 +1,81 @@
+mod chromium;
+mod copy;
 mod error;
+mod locale;
 
 use std::{
     cell::RefCell,
     env::var,
     fmt::Display,
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{Read, Write},
     net::SocketAddr,
     path::{Path, PathBuf},
     process::Command,
-    sync::{mpsc::Receiver, Arc, Mutex},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
 };
 
 use anyhow::Context;
 use axum::{
-    extract::{Extension, Query},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension, Query},
     http,
     http::status::StatusCode,
+    middleware::Next,
     response::{IntoResponse, Response},
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chromium::{Chromium, ImageFormat};
 use concisemark::{
     node::{Node, NodeTagName},
     Page,
 };
-use error::{Error, Result};
+use copy::CopyFormat;
+use error::{Error, ErrorMessage, Result};
+use locale::Locale;
 use nvim_agent::{NeovimClient, Value};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tracing_subscriber::fmt::writer::MakeWriter;
 
 const DEFAULT_PORT: u16 = 3008;
 const DEFUALT_HOST: &str = "127.0.0.1";
 const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+// copied fragments are pasted into documents, they always use the default theme rather than the
+// one of the preview
+const COPY_CSS_FILE: &str = "nvim-previewer-default.css";
+const TOKEN_HEADER: &str = "x-previewer-token";
+const ASSET_DIR: &str = "assets";
+const MAX_ASSET_SIZE: usize = 32 * 1024 * 1024;
+const MAX_ASSET_EXT_LEN: usize = 8;
+const DEFAULT_IMAGE_WIDTH: u32 = 1280;
+const MIN_IMAGE_WIDTH: u32 = 320;
+const MAX_IMAGE_WIDTH: u32 = 3840;
 static PREVIEW_FILE_PATH: Lazy<Arc<Mutex<Option<PathBuf>>>> =
     Lazy::new(|| Arc::new(Mutex::new(None)));
 static PREVIEW_CSS_PATH: Lazy<Arc<Mutex<Option<PathBuf>>>> =
     Lazy::new(|| Arc::new(Mutex::new(None)));
+static PREVIEW_ACCESSIBLE: Lazy<Arc<Mutex<bool>>> =
+    Lazy::new(|| Arc::new(Mutex::new(false)));
+// user interface locale of the previewed file, resolved when the file is previewed
+static PREVIEW_LOCALE: Lazy<Arc<Mutex<Option<&'static Locale>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(None)));
+// events sent by the web server to the agent, such as inserting text into neovim
+static AGENT_EVENT_SENDER: Lazy<Arc<Mutex<Option<Sender<Event>>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(None)));
+
+// an event name with its parameters, sent by neovim or the web server
+type Event = (String, Vec<Value>);
 
 #[derive(Deserialize)]
 enum FileTag {
     #[serde(rename = "css")]
-    CSS,
+    Css,
     #[serde(rename = "path")]
     Path,
 }
@@ -82,7 +116,7 @@ fn server(config: PreviewerConfig) -> Result<()> {
     let addr = format!("{DEFUALT_HOST}:{}", config.port)
         .parse::<SocketAddr>()
         .map_err(|e| anyerr!("failed to parse socket addr: {e:?}"))?;
-    log::info!("web server start to listen at {}", addr.to_string());
+    log::info!("web server start to listen at {addr}");
     let rt = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(5)
         .enable_all()
@@ -94,7 +128,16 @@ fn server(config: PreviewerConfig) -> Result<()> {
             .route("/ping", axum::routing::get(ping))
             .route("/pdf", axum::routing::get(render_as_pdf))
             .route("/file", axum::routing::get(file))
+            .route("/image", axum::routing::get(render_as_image))
+            .route("/api/copy", axum::routing::get(copy_as))
+            .route(
+                "/api/assets",
+                axum::routing::post(upload_asset)
+                    .layer(DefaultBodyLimit::max(MAX_ASSET_SIZE)),
+            )
             .fallback(fallback)
+            .layer(axum::middleware::from_fn(localize_error))
+            .layer(axum::middleware::from_fn(check_host))
             .layer(Extension(config));
         axum::Server::bind(&addr)
             .serve(app.into_make_service())
@@ -104,11 +147,80 @@ fn server(config: PreviewerConfig) -> Result<()> {
     Ok(())
 }
 
+/// Reject requests which are not addressed to the previewer itself, a page from another site
+/// resolving its domain to the loopback address (DNS rebinding) must not read the page token.
+async fn check_host<B>(
+    Extension(config): Extension<Arc<PreviewerConfig>>,
+    request: http::Request<B>,
+    next: Next<B>,
+) -> Response {
+    let host = request
+        .headers()
+        .get(http::header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let allowed = [
+        format!("{DEFUALT_HOST}:{}", config.port),
+        format!("localhost:{}", config.port),
+    ];
+    if !allowed.iter().any(|x| x == host) {
+        log::warn!("reject request to host: {host}");
+        return (StatusCode::FORBIDDEN, preview_locale(&config).invalid_host)
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Prefix the message of error responses with a localized description
+async fn localize_error<B>(
+    Extension(config): Extension<Arc<PreviewerConfig>>,
+    request: http::Request<B>,
+    next: Next<B>,
+) -> Response {
+    let resp = next.run(request).await;
+    let msg = match resp.extensions().get::<ErrorMessage>() {
+        Some(ErrorMessage(msg)) => msg.clone(),
+        None => return resp,
+    };
+    let locale = preview_locale(&config);
+    (resp.status(), Locale::fill(locale.request_failed, msg)).into_response()
+}
+
+/// Get the language of a document given by its `lang:` metadata
+fn document_lang(page: &Page, content: &str) -> Option<String> {
+    // a document without metadata may still have a line starting with `lang:`
+    page.meta
+        .as_ref()
+        .and_then(|_| locale::frontmatter_lang(content))
+}
+
+/// Resolve the user interface locale of a document, its language is preferred over the
+/// configured locale.
+fn document_locale(
+    config: &PreviewerConfig,
+    page: &Page,
+    content: &str,
+) -> &'static Locale {
+    config.locale(document_lang(page, content).as_deref())
+}
+
+/// Get the user interface locale of the previewed file, the configured one is used if no file is
+/// previewed.
+fn preview_locale(config: &PreviewerConfig) -> &'static Locale {
+    PREVIEW_LOCALE
+        .lock()
+        .unwrap()
+        .unwrap_or_else(|| config.locale(None))
+}
+
 async fn ping() -> impl IntoResponse {
     (http::status::StatusCode::OK, "").into_response()
 }
 
-async fn fallback(uri: http::Uri) -> impl IntoResponse {
+async fn fallback(
+    Extension(config): Extension<Arc<PreviewerConfig>>,
+    uri: http::Uri,
+) -> impl IntoResponse {
     let (status, mime, content) = match uri.to_string().as_str() {
         "/favicon.ico" => (
             StatusCode::OK,
@@ -120,7 +232,8 @@ async fn fallback(uri: http::Uri) -> impl IntoResponse {
             (
                 StatusCode::NOT_FOUND,
                 "text/plain",
-                format!("No route for {uri}").as_bytes().to_vec(),
+                Locale::fill(preview_locale(&config).no_route, uri.to_string())
+                    .into_bytes(),
             )
         }
     };
@@ -139,13 +252,16 @@ async fn file(
     filemeta: Query<FileMeta>,
 ) -> impl IntoResponse {
     let filepath = match filemeta.tag {
-        FileTag::CSS => {
+        FileTag::Css => {
             let path = PREVIEW_CSS_PATH.lock().unwrap();
             let p = path.clone();
             if let Some(pp) = p {
                 pp
             } else {
-                return (StatusCode::NOT_FOUND, "css file not found")
+                return (
+                    StatusCode::NOT_FOUND,
+                    preview_locale(&config).css_not_found,
+                )
                     .into_response();
             }
         }
@@ -166,7 +282,11 @@ async fn file(
     if content.is_empty() {
         mime = "text/plain";
         content.extend_from_slice(
-            format!("can not read file: {}", filepath.display()).as_bytes(),
+            Locale::fill(
+                preview_locale(&config).read_failed,
+                filepath.display().to_string(),
+            )
+            .as_bytes(),
         );
     }
     Response::builder()
@@ -179,6 +299,294 @@ async fn file(
         .unwrap()
 }
 
+#[derive(Deserialize)]
+struct CopyOptions {
+    format: Option<CopyFormat>,
+    start: Option<usize>,
+    end: Option<usize>,
+}
+
+/// Render `filepath` (or lines `start` to `end` of it, 1-based and inclusive) into a fragment
+/// styled by `csspath`, which can be pasted into mail or document editors. Local images are
+/// embedded as data uris since the previewer server is not reachable from where it is pasted.
+fn copy_fragment(
+    filepath: &Path,
+    csspath: Option<&Path>,
+    options: &CopyOptions,
+) -> Result<String> {
+    let mut content = String::new();
+    File::open(filepath)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .map_err(|e| {
+            anyerr!("failed to read file {}: {e:?}", filepath.display())
+        })?;
+    if options.start.is_some() || options.end.is_some() {
+        let start = options.start.unwrap_or(1).max(1) - 1;
+        let end = options.end.unwrap_or(usize::MAX);
+        content = content
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| *i >= start && *i < end)
+            .map(|(_, line)| line)
+            .collect::<Vec<&str>>()
+            .join("\n");
+    }
+    let filedir = filepath.parent().unwrap_or(filepath);
+    let page = Page::new(&content);
+    let image_src = |path: &Path| {
+        let data = fs::read(path).ok()?;
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        Some(format!("data:{mime};base64,{}", STANDARD.encode(data)))
+    };
+    let body = render_body(&page, &content, filedir, &image_src);
+
+    let mut css = String::new();
+    if let Some(csspath) = csspath {
+        if let Ok(mut f) = File::open(csspath) {
+            _ = f.read_to_string(&mut css);
+        }
+    }
+    let html = format!(
+        "<html><head><style>{css}</style></head><body>\
+        <div class=\"content\" id=\"content\">{body}</div></body></html>"
+    );
+    let inliner = css_inline::CSSInliner::options()
+        .load_remote_stylesheets(false)
+        .build();
+    let html = inliner
+        .inline(&html)
+        .map_err(|e| anyerr!("failed to inline css style: {e:?}"))?;
+    let fragment = copy::strip_attrs(copy::body_of(&html), &["class", "id"]);
+    match options.format.unwrap_or_default() {
+        CopyFormat::Html => Ok(fragment),
+        CopyFormat::Rtf => Ok(copy::html_to_rtf(&fragment)),
+    }
+}
+
+async fn copy_as(
+    options: Query<CopyOptions>,
+) -> Result<axum::response::Response> {
+    let format = options.format.unwrap_or_default();
+    let filepath = PREVIEW_FILE_PATH
+        .lock()
+        .map_err(|e| anyerr!("failed to lock: {e:?}"))?
+        .clone()
+        .ok_or(anyerr!("no previewed file"))?;
+    // all stylesheets live in the plugin directory
+    let csspath = PREVIEW_CSS_PATH
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|x| x.with_file_name(COPY_CSS_FILE));
+    let fragment = tokio::task::spawn_blocking(move || {
+        copy_fragment(&filepath, csspath.as_deref(), &options)
+    })
+    .await
+    .map_err(|e| anyerr!("failed to join copy task: {e:?}"))??;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_str(&format!(
+                "{}; charset=utf-8",
+                format.mime()
+            ))
+            .map_err(|e| anyerr!("failed to parse copy mime: {e:?}"))?,
+        )
+        .body(axum::body::boxed(axum::body::Full::from(fragment)))
+        .map_err(|e| anyerr!("failed to create copy response body: {e:?}"))?)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn notify_agent<S: AsRef<str>>(event: S, params: Vec<Value>) -> Result<()> {
+    let sender = AGENT_EVENT_SENDER
+        .lock()
+        .map_err(|e| anyerr!("failed to lock: {e:?}"))?;
+    let sender = sender.as_ref().ok_or(anyerr!("agent is not started"))?;
+    sender
+        .send((event.as_ref().to_owned(), params))
+        .map_err(|e| anyerr!("failed to notify agent: {e:?}"))?;
+    Ok(())
+}
+
+/// Make the file name `text` safe as the text of a markdown link, the link is inserted as a
+/// single line.
+fn link_text(text: &str) -> String {
+    text.replace(|c: char| c == '[' || c == ']' || c.is_control(), "")
+}
+
+#[derive(Deserialize)]
+struct AssetOptions {
+    name: Option<String>,
+}
+
+/// Save the posted file into the asset directory next to the previewed file, and insert the
+/// markdown link (or image link if the file is an image) into neovim at the cursor.
+async fn upload_asset(
+    Extension(config): Extension<Arc<PreviewerConfig>>,
+    options: Query<AssetOptions>,
+    headers: http::HeaderMap,
+    body: Bytes,
+) -> std::result::Result<Response, Response> {
+    // the page tells whether the upload is failed by the status
+    let internal_error = |e: anyhow::Error| {
+        Error::Other(e).into_response_with(StatusCode::INTERNAL_SERVER_ERROR)
+    };
+    let token = headers.get(TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if token != Some(config.token.as_str()) {
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            preview_locale(&config).invalid_token,
+        )
+            .into_response());
+    }
+    if body.is_empty() {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            preview_locale(&config).empty_asset,
+        )
+            .into_response());
+    }
+    let name = options
+        .name
+        .as_deref()
+        .filter(|x| !x.is_empty())
+        .map(Path::new);
+    // browsers may post files of unknown type as `application/octet-stream`
+    let guessed_mime = name.and_then(|x| mime_guess::from_path(x).first());
+    let mime = match headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(v) if !v.is_empty() && v != "application/octet-stream" => v,
+        _ => guessed_mime
+            .as_ref()
+            .map(|x| x.essence_str())
+            .unwrap_or("application/octet-stream"),
+    };
+    let is_image = mime.starts_with("image/");
+
+    // the extension is given by the client, keep it safe for file names and markdown links
+    let ext = name
+        .and_then(|x| x.extension())
+        .and_then(|x| x.to_str())
+        .filter(|x| {
+            !x.is_empty()
+                && x.len() <= MAX_ASSET_EXT_LEN
+                && x.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .or_else(|| match mime {
+            "image/jpeg" => Some("jpg"),
+            "image/svg+xml" => Some("svg"),
+            _ => mime_guess::get_mime_extensions_str(mime)
+                .and_then(|x| x.first().copied()),
+        })
+        .unwrap_or(if is_image { "png" } else { "bin" })
+        .to_lowercase();
+    let digest = Sha256::digest(&body);
+    let assetname = format!("{}.{ext}", to_hex(&digest[..16]));
+
+    let filepath =
+        PREVIEW_FILE_PATH.lock().unwrap().clone().ok_or_else(|| {
+            Error::Other(anyerr!("no previewed file"))
+                .into_response_with(StatusCode::CONFLICT)
+        })?;
+    let assetdir = filepath
+        .parent()
+        .ok_or(anyerr!("preview file has no parent directory"))
+        .map_err(internal_error)?
+        .join(ASSET_DIR);
+    fs::create_dir_all(&assetdir).map_err(|e| {
+        internal_error(anyerr!(
+            "failed to create {}: {e:?}",
+            assetdir.display()
+        ))
+    })?;
+    let assetpath = assetdir.join(&assetname);
+    // the same content is saved only once, but never link to a different file
+    if fs::read(&assetpath).ok().as_deref() != Some(&body[..]) {
+        fs::write(&assetpath, &body).map_err(|e| {
+            internal_error(anyerr!(
+                "failed to write {}: {e:?}",
+                assetpath.display()
+            ))
+        })?;
+    }
+    log::info!("asset is saved to {}", assetpath.display());
+
+    let link = if is_image {
+        let alt = name
+            .and_then(|x| x.file_stem())
+            .and_then(|x| x.to_str())
+            .map(link_text)
+            .unwrap_or_default();
+        format!("![{alt}]({ASSET_DIR}/{assetname})")
+    } else {
+        let text = name
+            .and_then(|x| x.file_name())
+            .and_then(|x| x.to_str())
+            .map(link_text)
+            .filter(|x| !x.is_empty())
+            .unwrap_or(assetname.clone());
+        format!("[{text}]({ASSET_DIR}/{assetname})")
+    };
+    // the link is relative to the previewed file, so it is only inserted into its buffer
+    let params = vec![
+        Value::from(link.as_str()),
+        Value::from(filepath.display().to_string()),
+    ];
+    notify_agent("insert", params)
+        .map_err(|e| e.into_response_with(StatusCode::SERVICE_UNAVAILABLE))?;
+    Ok((StatusCode::OK, link).into_response())
+}
+
+#[derive(Deserialize)]
+struct ImageOptions {
+    format: Option<ImageFormat>,
+    width: Option<u32>,
+}
+
+/// Capture the rendered page into a full-page image using headless chromium
+async fn render_as_image(
+    Extension(config): Extension<Arc<PreviewerConfig>>,
+    options: Query<ImageOptions>,
+) -> Result<axum::response::Response> {
+    let format = options.format.unwrap_or_default();
+    let width = options
+        .width
+        .unwrap_or(DEFAULT_IMAGE_WIDTH)
+        .clamp(MIN_IMAGE_WIDTH, MAX_IMAGE_WIDTH);
+    let image = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let chromium = Chromium::new(config.chromium.as_deref())?;
+        let workdir = tempfile::tempdir().map_err(|e| {
+            anyerr!("failed to create temporary directory: {e:?}")
+        })?;
+        let imagefile = workdir.path().join(format!("output.{}", format.ext()));
+        // the menu is useless in a picture of the document
+        let url =
+            format!("http://{DEFUALT_HOST}:{}/?export=image", config.port);
+        chromium.screenshot(&url, width, &imagefile)?;
+        let image = fs::read(&imagefile)
+            .map_err(|e| anyerr!("failed to read rendered image: {e:?}"))?;
+        log::info!("render image is done: {}", imagefile.display());
+        Ok(image)
+    })
+    .await
+    .map_err(|e| anyerr!("failed to join image task: {e:?}"))??;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_str(format.mime())
+                .map_err(|e| anyerr!("failed to parse image mime: {e:?}"))?,
+        )
+        .body(axum::body::boxed(axum::body::Full::from(image)))
+        .map_err(|e| anyerr!("failed to create image response body: {e:?}"))?)
+}
+
 #[derive(Deserialize)]
 struct PDFOptions {
     is_source: Option<bool>,
@@ -211,7 +619,8 @@ async fn render_as_pdf(
         .ok_or(anyerr!("preview file has no parent directory"))?;
     let workdir = tempfile::tempdir()
         .map_err(|e| anyerr!("failed to create temporary directory: {e:?}"))?;
-    let page = Page::new(content);
+    let page = Page::new(&content);
+    let locale = document_locale(&config, &page, &content);
     let hook = |node: &Node| -> Result<()> {
         let mut nodedata = node.data.borrow_mut();
         if nodedata.tag.name == NodeTagName::Image {
@@ -284,6 +693,18 @@ async fn render_as_pdf(
     page.transform(hook);
 
     let latex = page.render_latex();
+    // override the date of title block with the localized one
+    let latex = match page.meta.as_ref() {
+        Some(meta) => latex.replacen(
+            "\\maketitle",
+            &format!(
+                "\\date{{{}}}\\maketitle",
+                meta.date.format(locale.date_format)
+            ),
+            1,
+        ),
+        None => latex,
+    };
     let texfile = workdir.path().join("output.tex");
     let mut f = OpenOptions::new()
         .truncate(true)
@@ -342,10 +763,117 @@ async fn render_as_pdf(
     }
 }
 
+/// Render the body of `page` into html, `content` is the source text of the page and `filedir` is
+/// the directory used to resolve relative image paths, the source of local images is rewritten by
+/// `image_src`.
+fn render_body(
+    page: &Page,
+    content: &str,
+    filedir: &Path,
+    image_src: &dyn Fn(&Path) -> Option<String>,
+) -> String {
+    let hook = |node: &Node| -> Result<()> {
+        let mut nodedata = node.data.borrow_mut();
+        if nodedata.tag.name == NodeTagName::Image {
+            let src = if let Some(src) = nodedata.tag.attrs.get("src") {
+                src.to_owned()
+            } else {
+                "".to_owned()
+            };
+            let local_filepath = filedir.join(src);
+            if local_filepath.exists() {
+                if let Some(src) = image_src(&local_filepath) {
+                    nodedata.tag.attrs.insert("src".to_owned(), src);
+                }
+            }
+        }
+        Ok(())
+    };
+    page.transform(hook);
+    let hook = |node: &Node| -> Option<String> {
+        let nodedata = node.data.borrow_mut();
+        if nodedata.tag.name == NodeTagName::Code {
+            let (s, e) = (nodedata.range.start, nodedata.range.end);
+            let code = content[s..e].to_owned();
+            let code = code.trim_matches(|c| c == '`');
+            if nodedata.tag.attrs.contains_key("inlined") {
+                return None;
+            }
+            let code = concisemark::utils::remove_indent(code);
+            if let Ok(code) = code_highlight(&code, None::<&str>) {
+                return Some(code);
+            }
+            return Some(code.to_owned());
+        }
+        None
+    };
+    page.render_with_hook(&hook)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Find the images without alternative text in `page` and report them as a list of warnings
+fn check_image_alt(page: &Page, locale: &Locale) -> String {
+    let missing = RefCell::new(vec![]);
+    let hook = |node: &Node| -> Result<()> {
+        let nodedata = node.data.borrow();
+        if nodedata.tag.name == NodeTagName::Image {
+            let described = ["alt", "name"].iter().any(|k| {
+                nodedata
+                    .tag
+                    .attrs
+                    .get(*k)
+                    .map(|v| !v.trim().is_empty())
+                    .unwrap_or(false)
+            });
+            if !described {
+                let src = nodedata.tag.attrs.get("src").cloned();
+                missing.borrow_mut().push(src.unwrap_or_default());
+            }
+        }
+        Ok(())
+    };
+    page.transform(hook);
+
+    let missing = missing.into_inner();
+    if missing.is_empty() {
+        return "".to_owned();
+    }
+    let items = missing
+        .iter()
+        .map(|src| {
+            let src = format!("<code>{}</code>", escape_html(src));
+            format!("<li>{}</li>", Locale::fill(locale.missing_alt, src))
+        })
+        .collect::<String>();
+    format!(
+        "<aside class=\"a11y-warnings\" role=\"complementary\" \
+        aria-labelledby=\"a11y-warnings-title\">\
+        <h2 id=\"a11y-warnings-title\">{}</h2><ul>{items}</ul></aside>",
+        locale.a11y_warnings
+    )
+}
+
+#[derive(Deserialize)]
+struct RenderOptions {
+    export: Option<String>,
+}
+
 async fn render(
     Extension(config): Extension<Arc<PreviewerConfig>>,
+    options: Query<RenderOptions>,
 ) -> impl IntoResponse {
+    let hide_menu = options.export.is_some();
+    let accessible = *PREVIEW_ACCESSIBLE.lock().unwrap();
     let mut meta = None;
+    let mut warnings = String::new();
+    let mut doclang = None;
+    let mut locale = config.locale(None);
     let html = match PREVIEW_FILE_PATH.lock().unwrap().as_ref() {
         Some(path) => {
             log::info!("start to render file: {}", path.display());
@@ -355,69 +883,79 @@ async fn render(
                 _ = f.read_to_string(&mut content);
                 let page = Page::new(&content);
                 meta = page.meta.clone();
-                let hook = |node: &Node| -> Result<()> {
-                    let mut nodedata = node.data.borrow_mut();
-                    if nodedata.tag.name == NodeTagName::Image {
-                        let src =
-                            if let Some(src) = nodedata.tag.attrs.get("src") {
-                                src.to_owned()
-                            } else {
-                                "".to_owned()
-                            };
-                        let local_filepath = filedir.join(src);
-                        if local_filepath.exists() {
-                            let src = format!(
-                                "http://{DEFUALT_HOST}:{}/file?tag=path&val={}",
-                                config.port,
-                                local_filepath.display(),
-                            );
-                            nodedata.tag.attrs.insert("src".to_owned(), src);
-                        }
-                    }
-                    Ok(())
-                };
-                page.transform(hook);
-                let hook = |node: &Node| -> Option<String> {
-                    let nodedata = node.data.borrow_mut();
-                    if nodedata.tag.name == NodeTagName::Code {
-                        let (s, e) = (nodedata.range.start, nodedata.range.end);
-                        let code = content[s..e].to_owned();
-                        let code = code.trim_matches(|c| c == '`');
-                        if nodedata.tag.attrs.contains_key("inlined") {
-                            return None;
-                        }
-                        let code = concisemark::utils::remove_indent(code);
-                        if let Ok(code) = code_highlight(&code, None::<&str>) {
-                            return Some(code);
-                        }
-                        return Some(code.to_owned());
-                    }
-                    None
+                doclang = document_lang(&page, &content);
+                locale = config.locale(doclang.as_deref());
+                if accessible {
+                    warnings = check_image_alt(&page, locale);
+                }
+                let image_src = |path: &Path| {
+                    Some(format!(
+                        "http://{DEFUALT_HOST}:{}/file?tag=path&val={}",
+                        config.port,
+                        path.display(),
+                    ))
                 };
-                page.render_with_hook(&hook)
+                render_body(&page, &content, filedir, &image_src)
             } else {
-                format!("failed to open file: {}", path.display())
+                Locale::fill(locale.open_failed, path.display().to_string())
             }
         }
-        None => "no file to render".to_owned(),
+        None => locale.no_file.to_owned(),
     };
-    let (title, subtitle, date) = if let Some(meta) = meta {
+    let (title, subtitle, date, iso_date) = if let Some(meta) = meta {
         let title = meta.title;
         let subtitle = meta.subtitle.unwrap_or("".to_owned());
-        let date = format!("{}", meta.date.format("%Y-%m-%d %H:%M:%S"));
-        (title, subtitle, date)
+        let date = format!("{}", meta.date.format(locale.date_format));
+        let iso_date = format!("{}", meta.date.format("%Y-%m-%dT%H:%M:%S"));
+        (title, subtitle, date, iso_date)
     } else {
-        ("".to_owned(), "".to_owned(), "".to_owned())
+        Default::default()
+    };
+    // the page is in the language of the document, which may be unsupported by the user interface
+    let lang = match doclang.as_deref().or(config.locale.as_deref()) {
+        Some(lang) => format!(" lang=\"{}\"", escape_html(lang)),
+        None => "".to_owned(),
+    };
+    // the templates share the same placeholders except the `extra` ones
+    macro_rules! fill_template {
+        ($template:literal $(, $extra:ident = $value:expr)*) => {
+            format!(
+                include_str!($template),
+                title = title,
+                script = include_str!("../plugin/nvim-previewer.js"),
+                gap = if subtitle.is_empty() { "" } else { " - " },
+                subtitle = subtitle,
+                token = config.token,
+                hide_menu = if hide_menu { " hidden" } else { "" },
+                body = html,
+                lang = lang,
+                view_pdf = locale.view_pdf,
+                view_latex = locale.view_latex,
+                view_image = locale.view_image,
+                copy_html = locale.copy_html,
+                $($extra = $value,)*
+            )
+        };
+    }
+    let html_template = if accessible {
+        // an empty `<time>` is invalid, so the line is left out without a date
+        let date_line = if iso_date.is_empty() {
+            "".to_owned()
+        } else {
+            format!(
+                "<p class=\"meta\"><time datetime=\"{iso_date}\">{date}</time></p>"
+            )
+        };
+        fill_template!(
+            "../plugin/index-a11y.html",
+            warnings = warnings,
+            date_line = date_line,
+            skip_to_content = locale.skip_to_content,
+            menu = locale.menu
+        )
+    } else {
+        fill_template!("../plugin/index.html", date = date)
     };
-    let html_template = format!(
-        include_str!("../plugin/index.html"),
-        title = title,
-        script = include_str!("../plugin/nvim-previewer.js"),
-        gap = if subtitle.is_empty() { "" } else { " - " },
-        subtitle = subtitle,
-        date = date,
-        body = html,
-    );
 
     let url = css_inline::Url::parse(&format!(
         "http://{DEFUALT_HOST}:{}",
@@ -454,13 +992,25 @@ async fn render(
 pub struct PreviewerConfig {
     pub browser: Option<String>,
     pub port: u16,
+    /// chromium binary used to capture images of the rendered page
+    pub chromium: Option<String>,
+    /// language of the user interface, overridden by `lang:` of the document
+    pub locale: Option<String>,
+    /// token required by the apis which modify files or neovim buffers
+    pub token: String,
 }
 
 impl Default for PreviewerConfig {
     fn default() -> Self {
+        let mut token = [0u8; 16];
+        getrandom::getrandom(&mut token)
+            .expect("failed to generate previewer token");
         PreviewerConfig {
             browser: None,
             port: DEFAULT_PORT,
+            chromium: None,
+            locale: None,
+            token: to_hex(&token),
         }
     }
 }
@@ -472,21 +1022,42 @@ impl Display for PreviewerConfig {
             msg.push_str(&format!("\nbrowser: {browser}\n"));
         }
         msg.push_str(&format!("port: {}\n", self.port));
+        if let Some(chromium) = self.chromium.as_ref() {
+            msg.push_str(&format!("chromium: {chromium}\n"));
+        }
+        if let Some(locale) = self.locale.as_ref() {
+            msg.push_str(&format!("locale: {locale}\n"));
+        }
         f.write_str(&msg)
     }
 }
 
 impl PreviewerConfig {
-    pub fn new<S1, S2>(browser: S1, port: S2) -> Self
+    pub fn new<S1, S2, S3, S4>(
+        browser: S1,
+        port: S2,
+        chromium: S3,
+        locale: S4,
+    ) -> Self
     where
         S1: AsRef<str>,
         S2: AsRef<str>,
+        S3: AsRef<str>,
+        S4: AsRef<str>,
     {
         let (browser, port) = (browser.as_ref().trim(), port.as_ref().trim());
+        let (chromium, locale) =
+            (chromium.as_ref().trim(), locale.as_ref().trim());
         let mut config = PreviewerConfig::default();
         if !browser.is_empty() {
             config.browser = Some(browser.to_owned());
         }
+        if !chromium.is_empty() {
+            config.chromium = Some(chromium.to_owned());
+        }
+        if !locale.is_empty() {
+            config.locale = Some(locale.to_owned());
+        }
         if let Ok(v) = port.parse::<u16>() {
             if v > 1024 {
                 config.port = v
@@ -494,41 +1065,53 @@ impl PreviewerConfig {
         }
         config
     }
+
+    /// Get the user interface locale for a document written in `doclang`
+    pub fn locale(&self, doclang: Option<&str>) -> &'static Locale {
+        Locale::select(doclang, self.locale.as_deref())
+    }
 }
 
 struct Previewer {
     client: RefCell<NeovimClient>,
     config: PreviewerConfig,
-    receiver: Receiver<(String, Vec<Value>)>,
+    receiver: Receiver<Event>,
     logdir: PathBuf,
-    cachedir: PathBuf,
 }
 
 impl Previewer {
     pub fn new(mut client: NeovimClient) -> Self {
-        let receiver = client.start();
+        // merge the notifications from neovim with the events from web server
+        let (sender, receiver) = mpsc::channel();
+        let notifications = client.start();
+        let forwarder = sender.clone();
+        std::thread::spawn(move || {
+            for notification in notifications {
+                if forwarder.send(notification).is_err() {
+                    break;
+                }
+            }
+        });
+        *AGENT_EVENT_SENDER.lock().unwrap() = Some(sender);
 
         let cachedir =
             PathBuf::from(client.eval("stdpath('cache')")).join(PKG_NAME);
         let browser = client.eval("g:nvim_previewer_browser");
         let port = client.eval("g:nvim_previewer_port");
+        let chromium = client.eval("g:nvim_previewer_chromium");
+        let locale = client.eval("g:nvim_previewer_locale");
         Self {
             receiver,
-            config: PreviewerConfig::new(browser, port),
+            config: PreviewerConfig::new(browser, port, chromium, locale),
             client: RefCell::new(client),
             logdir: cachedir.join("logs"),
-            cachedir,
         }
     }
 
-    pub fn recv(&self) -> &Receiver<(String, Vec<Value>)> {
+    pub fn recv(&self) -> &Receiver<Event> {
         &self.receiver
     }
 
-    pub fn eval<S: AsRef<str>>(&self, vimcmd: S) -> String {
-        self.client.borrow_mut().eval(vimcmd.as_ref())
-    }
-
     fn preview(&self) -> Result<()> {
         let url = format!("http://{DEFUALT_HOST}:{}", self.config.port);
         let r = if let Some(browser) = &self.config.browser {
@@ -553,6 +1136,68 @@ impl Previewer {
         Ok(())
     }
 
+    /// Copy the rendered document onto the system clipboard, `params` are the file path, the
+    /// plugin directory, the copy format and the optional line range of the source.
+    ///
+    /// The previewed file is left untouched, so copying from another buffer does not change what
+    /// the browser shows.
+    fn copy(&self, params: &[Value]) -> Result<()> {
+        let filepath = params
+            .first()
+            .and_then(|x| x.as_str())
+            .map(PathBuf::from)
+            .ok_or(anyerr!("no file to be copied"))?;
+        let script_dir = params
+            .get(1)
+            .and_then(|x| x.as_str())
+            .ok_or(anyerr!("failed to find nvim-previewer plugin directory"))?;
+        let format = params
+            .get(2)
+            .and_then(|x| x.as_str())
+            .map(CopyFormat::new)
+            .unwrap_or_default();
+        let options = CopyOptions {
+            format: Some(format),
+            start: params.get(3).and_then(|x| x.as_u64()).map(|x| x as usize),
+            end: params.get(4).and_then(|x| x.as_u64()).map(|x| x as usize),
+        };
+        let csspath = Path::new(script_dir).join(COPY_CSS_FILE);
+        let fragment =
+            copy_fragment(&filepath, Some(csspath.as_path()), &options)?;
+        if let Err(e) = copy::set_system_clipboard(&fragment, format) {
+            log::warn!("fallback to clipboard register: {e:?}");
+            self.client
+                .borrow_mut()
+                .setreg("+", &fragment)
+                .map_err(|e| anyerr!("failed to set clipboard: {e:?}"))?;
+        }
+        self.print(format!("rendered document is copied as {format:?}"));
+        Ok(())
+    }
+
+    /// Insert the text given by the first parameter at the cursor, the text is dropped if the
+    /// current buffer is not the file given by the optional second parameter.
+    fn insert(&self, params: &[Value]) -> Result<()> {
+        let text = params
+            .first()
+            .and_then(|x| x.as_str())
+            .ok_or(anyerr!("no text to be inserted"))?;
+        if let Some(target) = params.get(1).and_then(|x| x.as_str()) {
+            let current = self.client.borrow_mut().eval("expand('%:p')");
+            if Path::new(&current) != Path::new(target) {
+                return Err(anyerr!(
+                    "{text} is not inserted, switch to {target} to insert it"
+                )
+                .into());
+            }
+        }
+        self.client
+            .borrow_mut()
+            .put(text)
+            .map_err(|e| anyerr!("failed to insert text: {e:?}"))?;
+        Ok(())
+    }
+
     pub fn print<S: AsRef<str>>(&self, msg: S) {
         self.client.borrow_mut().print(msg.as_ref());
     }
@@ -587,8 +1232,21 @@ async fn main() {
     log::info!("server started with configuration: {}", previewer.config);
 
     for (event, params) in previewer.recv() {
+        if event == "insert" {
+            if let Err(e) = previewer.insert(&params) {
+                previewer.print(format!("{e:?}"));
+            }
+            continue;
+        }
+        if event == "copy" {
+            if let Err(e) = previewer.copy(&params) {
+                previewer.print(format!("{e:?}"));
+            }
+            continue;
+        }
+
         let file_path = if let Some(Some(p)) =
-            params.get(0).map(|x| x.as_str().map(|x| x.to_owned()))
+            params.first().map(|x| x.as_str().map(|x| x.to_owned()))
         {
             p
         } else {
@@ -615,6 +1273,9 @@ async fn main() {
             "preview_alt" => {
                 Path::new(&script_dir).join("nvim-previewer-alt.css")
             }
+            "preview_a11y" => {
+                Path::new(&script_dir).join("nvim-previewer-a11y.css")
+            }
             _ => Path::new(&script_dir).join("nvim-previewer-default.css"),
         };
         log::info!("css file path: {}", css_file_path.display());
@@ -622,6 +1283,16 @@ async fn main() {
             let mut path = PREVIEW_CSS_PATH.lock().unwrap();
             *path = Some(css_file_path);
         }
+        *PREVIEW_ACCESSIBLE.lock().unwrap() = event == "preview_a11y";
+        // resolved only once here since error responses of the server use it
+        *PREVIEW_LOCALE.lock().unwrap() =
+            fs::read_to_string(&file_path).ok().map(|content| {
+                document_locale(
+                    &previewer.config,
+                    &Page::new(&content),
+                    &content,
+                )
+            });
 
         if let Err(e) = previewer.preview() {
             previewer.print(format!("{e:?}"));